@@ -1,26 +1,155 @@
-use std::collections::HashMap;
+// `main` only exercises a slice of this binary's API as a demo; snapshots,
+// persistence and the path-based helpers are covered by the test suite
+// instead. FUSE mounting (`mount`, `FuseAdapter`) is exercised by neither.
+#![allow(dead_code)]
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::{DateTime, Datelike, Utc};
+use fuser::{
+    FileAttr, FileType as FuseFileType, Filesystem as FuseFilesystem, MountOption, ReplyAttr,
+    ReplyData, ReplyDirectory, ReplyEntry, ReplyWrite, Request as FuseRequest,
+};
+use serde::{Deserialize, Serialize};
 
 const NUM_DIRECT_POINTERS: usize = 5;
 
-#[derive(Clone, Debug, PartialEq)]
+// Fixed-size unit of file storage. Block id 0 is reserved to mean "unused
+// pointer slot" so it is never handed out by the allocator.
+const BLOCK_SIZE: usize = 4096;
+
+// How many block ids fit in one indirect block (one little-endian `u64` per
+// slot).
+const IDS_PER_BLOCK: usize = BLOCK_SIZE / std::mem::size_of::<u64>();
+
+// Direct pointers, plus a single indirect block, plus a double-indirect
+// block of indirect blocks: the largest file `write_blocks` can represent
+// without a third level of indirection. `write_blocks` clamps to this
+// instead of panicking once a write would need one more level than exists.
+const MAX_FILE_BLOCKS: usize = NUM_DIRECT_POINTERS + IDS_PER_BLOCK + IDS_PER_BLOCK * IDS_PER_BLOCK;
+const MAX_FILE_SIZE: usize = MAX_FILE_BLOCKS * BLOCK_SIZE;
+
+// FUSE reserves inode 1 for the mount root, so the in-memory root directory
+// is always allocated that id and `next_id` starts past it.
+const ROOT_INODE_ID: u64 = 1;
+const TTL: Duration = Duration::from_secs(1);
+
+// Bumped whenever the on-disk image layout changes; `load` refuses to read
+// an image written by an incompatible version.
+const IMAGE_FORMAT_VERSION: u8 = 1;
+
+/// Packs block ids into an indirect block, one little-endian `u64` per slot.
+/// `ids` must fit within `IDS_PER_BLOCK`; callers are responsible for
+/// chunking a longer list across multiple indirect blocks first.
+fn encode_block_ids(ids: &[u64]) -> [u8; BLOCK_SIZE] {
+    assert!(ids.len() <= IDS_PER_BLOCK, "too many block ids for a single indirect block");
+    let mut block = [0u8; BLOCK_SIZE];
+    for (i, id) in ids.iter().enumerate() {
+        let offset = i * std::mem::size_of::<u64>();
+        block[offset..offset + std::mem::size_of::<u64>()].copy_from_slice(&id.to_le_bytes());
+    }
+    block
+}
+
+/// Reverses `encode_block_ids`, skipping unused (zero) slots.
+fn decode_block_ids(block: &[u8; BLOCK_SIZE]) -> Vec<u64> {
+    block
+        .chunks_exact(std::mem::size_of::<u64>())
+        .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+        .filter(|&id| id != 0)
+        .collect()
+}
+
+/// Splits a `/`-separated path into its non-empty components, so a leading
+/// or trailing `/` (or a relative path with none at all) are all accepted.
+fn split_path(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|s| !s.is_empty())
+}
+
+/// Splits a path into its parent (possibly empty, meaning the root) and its
+/// final component. Returns `None` for a path with no name component (`""`
+/// or `"/"`).
+fn split_parent(path: &str) -> Option<(&str, &str)> {
+    let trimmed = path.trim_end_matches('/');
+    if trimmed.is_empty() {
+        return None;
+    }
+    match trimmed.rfind('/') {
+        Some(idx) => Some((&trimmed[..idx], &trimmed[idx + 1..])),
+        None => Some(("", trimmed)),
+    }
+}
+
+/// Given snapshots sorted newest-first, keeps the newest one in each of the
+/// first `limit` distinct buckets `key_fn` produces, and returns their
+/// names. Used to implement each of the daily/weekly/monthly/yearly
+/// retention passes in `FileSystem::prune_backups`.
+fn select_into_buckets<K: Eq + std::hash::Hash>(
+    newest_first: &[&Snapshot],
+    limit: usize,
+    key_fn: impl Fn(&&Snapshot) -> K,
+) -> HashSet<String> {
+    let mut seen_buckets = HashSet::new();
+    let mut kept = HashSet::new();
+    for snapshot in newest_first {
+        if seen_buckets.len() >= limit {
+            break;
+        }
+        if seen_buckets.insert(key_fn(snapshot)) {
+            kept.insert(snapshot.name.clone());
+        }
+    }
+    kept
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 enum FileType {
     RegularFile,
     Directory,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 struct Inode {
     id: u64,
     name: String,
     size: u64,
     file_type: FileType,
     direct_pointers: [Option<u64>; NUM_DIRECT_POINTERS],
+    indirect_pointer: Option<u64>,
+    double_indirect_pointer: Option<u64>,
     entries: Option<Vec<u64>>, // For directories only
-    data: Option<Vec<u8>>,     // For storing file content
 }
 
+/// The result of `FileSystem::stat`: just enough to answer "what is this
+/// path" without handing out the full, mutable `Inode`.
+#[derive(Clone, Debug, PartialEq)]
+struct InodeMeta {
+    id: u64,
+    size: u64,
+    file_type: FileType,
+}
+
+/// A single recorded mutation. Each variant carries enough of a before-image
+/// to be reversed by `undo_last_operation`, and `TxnBegin`/`TxnCommit` mark
+/// the boundaries of an atomic group of operations for `replay`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+enum Operation {
+    TxnBegin,
+    TxnCommit,
+    CreateFile { id: u64, name: String },
+    CreateDir { id: u64, name: String },
+    AddEntry { child: u64, parent: u64 },
+    Write { id: u64, before: Option<Vec<u8>>, after: Vec<u8> },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 struct JournalEntry {
-    operation: String,
+    operation: Operation,
     committed: bool,
 }
 
@@ -33,42 +162,473 @@ impl Journal {
         Self { entries: Vec::new() }
     }
 
-    fn log(&mut self, operation: String) {
+    /// Opens a new transaction. Everything logged after this and before the
+    /// matching `commit_txn` is only applied by `replay` once the commit
+    /// marker is seen.
+    fn begin_txn(&mut self) {
+        self.entries.push(JournalEntry {
+            operation: Operation::TxnBegin,
+            committed: false,
+        });
+    }
+
+    fn log(&mut self, operation: Operation) {
         self.entries.push(JournalEntry {
             operation,
-            committed: true,
+            committed: false,
         });
     }
 
-    fn undo(&mut self) -> Option<String> {
-        if let Some(entry) = self.entries.pop() {
-            Some(entry.operation)
-        } else {
-            None
+    /// Closes the current transaction and marks every entry back to its
+    /// `TxnBegin` (inclusive) as committed.
+    fn commit_txn(&mut self) {
+        self.entries.push(JournalEntry {
+            operation: Operation::TxnCommit,
+            committed: false,
+        });
+        for entry in self.entries.iter_mut().rev() {
+            let is_begin = matches!(entry.operation, Operation::TxnBegin);
+            entry.committed = true;
+            if is_begin {
+                break;
+            }
         }
     }
 
+    fn undo(&mut self) -> Option<Operation> {
+        self.entries.pop().map(|entry| entry.operation)
+    }
+
     fn print_journal(&self) {
         println!("Journal Entries:");
         for (i, entry) in self.entries.iter().enumerate() {
-            println!("{}. {} [Committed: {}]", i + 1, entry.operation, entry.committed);
+            println!("{}. {:?} [Committed: {}]", i + 1, entry.operation, entry.committed);
         }
     }
 }
 
 struct FileSystem {
     next_id: u64,
-    inodes: HashMap<u64, Inode>,
+    // Wrapped in `Rc` so `snapshot` can share unchanged inodes with the live
+    // table instead of deep-cloning it; a write copy-on-writes its inode via
+    // `inode_mut`.
+    inodes: HashMap<u64, Rc<Inode>>,
     journal: Journal,
+    blocks: HashMap<u64, [u8; BLOCK_SIZE]>,
+    free_blocks: Vec<u64>,
+    next_block_id: u64,
+    snapshots: Vec<Snapshot>,
+}
+
+/// The durable payload written by `FileSystem::save`. The journal is
+/// intentionally excluded: an image is a snapshot of inode and block state,
+/// not a replayable log. Blocks are stored as `Vec<u8>` rather than
+/// `[u8; BLOCK_SIZE]` because serde only implements `Serialize`/
+/// `Deserialize` for arrays up to length 32.
+#[derive(Serialize, Deserialize)]
+struct FileSystemImage {
+    next_id: u64,
+    inodes: HashMap<u64, Inode>,
+    blocks: HashMap<u64, Vec<u8>>,
+    free_blocks: Vec<u64>,
+    next_block_id: u64,
+}
+
+/// A point-in-time copy of the inode and block state, captured by
+/// `FileSystem::snapshot`. Cloning `inodes` is cheap: it only bumps `Rc`
+/// refcounts for inodes that haven't changed since the snapshot was taken.
+struct Snapshot {
+    name: String,
+    timestamp: u64,
+    next_id: u64,
+    inodes: HashMap<u64, Rc<Inode>>,
+    blocks: HashMap<u64, [u8; BLOCK_SIZE]>,
+    free_blocks: Vec<u64>,
+    next_block_id: u64,
 }
 
 impl FileSystem {
     fn new() -> Self {
-        Self {
+        let mut fs = Self {
             next_id: 1,
             inodes: HashMap::new(),
             journal: Journal::new(),
+            blocks: HashMap::new(),
+            free_blocks: Vec::new(),
+            next_block_id: 1,
+            snapshots: Vec::new(),
+        };
+        fs.ensure_root();
+        fs
+    }
+
+    /// Returns a unique mutable reference to an inode, cloning it out of its
+    /// `Rc` the first time it's touched after a snapshot (copy-on-write).
+    fn inode_mut(&mut self, id: u64) -> Option<&mut Inode> {
+        Some(Rc::make_mut(self.inodes.get_mut(&id)?))
+    }
+
+    /// Makes sure the reserved root directory (inode 1) exists, without
+    /// going through the journal since it is bootstrap state rather than a
+    /// user-initiated operation.
+    fn ensure_root(&mut self) -> u64 {
+        if let std::collections::hash_map::Entry::Vacant(entry) = self.inodes.entry(ROOT_INODE_ID) {
+            let root = Inode {
+                id: ROOT_INODE_ID,
+                name: "/".to_string(),
+                size: 0,
+                file_type: FileType::Directory,
+                direct_pointers: [None; NUM_DIRECT_POINTERS],
+                indirect_pointer: None,
+                double_indirect_pointer: None,
+                entries: Some(Vec::new()),
+            };
+            entry.insert(Rc::new(root));
+            if self.next_id <= ROOT_INODE_ID {
+                self.next_id = ROOT_INODE_ID + 1;
+            }
         }
+        ROOT_INODE_ID
+    }
+
+    /// Pulls a block id off the free list, or mints a new one.
+    fn alloc_block(&mut self) -> u64 {
+        self.free_blocks.pop().unwrap_or_else(|| {
+            let id = self.next_block_id;
+            self.next_block_id += 1;
+            id
+        })
+    }
+
+    /// Releases every block currently owned by `file_id` (its direct
+    /// pointers, the indirect and double-indirect pointers' data blocks, and
+    /// the indirect/double-indirect blocks themselves) back onto the free
+    /// list.
+    fn free_file_blocks(&mut self, file_id: u64) {
+        let Some(inode) = self.inodes.get(&file_id) else {
+            return;
+        };
+
+        let mut block_ids: Vec<u64> = inode.direct_pointers.iter().filter_map(|p| *p).collect();
+        if let Some(indirect_id) = inode.indirect_pointer {
+            if let Some(indirect_block) = self.blocks.get(&indirect_id) {
+                block_ids.extend(decode_block_ids(indirect_block));
+            }
+            block_ids.push(indirect_id);
+        }
+        if let Some(double_indirect_id) = inode.double_indirect_pointer {
+            if let Some(double_indirect_block) = self.blocks.get(&double_indirect_id) {
+                for indirect_id in decode_block_ids(double_indirect_block) {
+                    if let Some(indirect_block) = self.blocks.get(&indirect_id) {
+                        block_ids.extend(decode_block_ids(indirect_block));
+                    }
+                    block_ids.push(indirect_id);
+                }
+            }
+            block_ids.push(double_indirect_id);
+        }
+
+        for id in block_ids {
+            self.blocks.remove(&id);
+            self.free_blocks.push(id);
+        }
+    }
+
+    /// Splits `data` into `BLOCK_SIZE` blocks, allocates storage for them,
+    /// and records the resulting block ids across `direct_pointers`, a
+    /// single indirect block, and (once the file outgrows that too) a
+    /// double-indirect block of indirect blocks — the same UFS-style layout
+    /// as before, extended with one more level. This still has a ceiling
+    /// (`MAX_FILE_SIZE`, set by how many ids a double-indirect block can
+    /// address), but a write past it is truncated rather than panicking.
+    /// Any blocks previously owned by `file_id` are freed first.
+    fn write_blocks(&mut self, file_id: u64, data: &[u8]) {
+        self.free_file_blocks(file_id);
+
+        let data = if data.len() > MAX_FILE_SIZE {
+            println!(
+                "Error: data ({} bytes) exceeds the maximum file size of {} bytes; truncating",
+                data.len(),
+                MAX_FILE_SIZE
+            );
+            &data[..MAX_FILE_SIZE]
+        } else {
+            data
+        };
+
+        let mut pointers = Vec::new();
+        for chunk in data.chunks(BLOCK_SIZE) {
+            let block_id = self.alloc_block();
+            let mut block = [0u8; BLOCK_SIZE];
+            block[..chunk.len()].copy_from_slice(chunk);
+            self.blocks.insert(block_id, block);
+            pointers.push(block_id);
+        }
+
+        let mut remaining = pointers.into_iter();
+        let mut direct_pointers = [None; NUM_DIRECT_POINTERS];
+        for slot in direct_pointers.iter_mut() {
+            *slot = remaining.next();
+        }
+
+        let mut overflow: Vec<u64> = remaining.collect();
+        let indirect_pointer = if overflow.is_empty() {
+            None
+        } else {
+            let single: Vec<u64> = overflow.drain(..overflow.len().min(IDS_PER_BLOCK)).collect();
+            let indirect_id = self.alloc_block();
+            self.blocks.insert(indirect_id, encode_block_ids(&single));
+            Some(indirect_id)
+        };
+
+        let double_indirect_pointer = if overflow.is_empty() {
+            None
+        } else {
+            let indirect_ids: Vec<u64> = overflow
+                .chunks(IDS_PER_BLOCK)
+                .map(|chunk| {
+                    let indirect_id = self.alloc_block();
+                    self.blocks.insert(indirect_id, encode_block_ids(chunk));
+                    indirect_id
+                })
+                .collect();
+            let double_indirect_id = self.alloc_block();
+            self.blocks.insert(double_indirect_id, encode_block_ids(&indirect_ids));
+            Some(double_indirect_id)
+        };
+
+        if let Some(inode) = self.inode_mut(file_id) {
+            inode.direct_pointers = direct_pointers;
+            inode.indirect_pointer = indirect_pointer;
+            inode.double_indirect_pointer = double_indirect_pointer;
+            inode.size = data.len() as u64;
+        }
+    }
+
+    /// Mounts this filesystem at `mountpoint` via FUSE, blocking until it is
+    /// unmounted. The in-memory inode table is adapted to the kernel's
+    /// lookup/getattr/read/readdir/write calls by `FuseAdapter`.
+    fn mount(&mut self, mountpoint: &Path) {
+        self.ensure_root();
+        let options = vec![MountOption::RW, MountOption::FSName("simple_file_system".to_string())];
+        fuser::mount2(FuseAdapter { fs: self }, mountpoint, &options).expect("failed to mount filesystem");
+    }
+
+    /// Looks up the id of the inode that `path` names, walking component by
+    /// component from the root. Returns `None` on any missing component
+    /// instead of panicking.
+    fn resolve(&self, path: &str) -> Option<u64> {
+        let mut current = ROOT_INODE_ID;
+        if !self.inodes.contains_key(&current) {
+            return None;
+        }
+
+        for name in split_path(path) {
+            let entries = self.inodes.get(&current)?.entries.as_ref()?;
+            current = entries
+                .iter()
+                .copied()
+                .find(|id| self.inodes.get(id).is_some_and(|inode| inode.name == name))?;
+        }
+        Some(current)
+    }
+
+    /// Finds the existing child of `dir_id` named `name`, if any.
+    fn find_child(&self, dir_id: u64, name: &str) -> Option<u64> {
+        self.inodes.get(&dir_id)?.entries.as_ref()?.iter().copied().find(|id| {
+            self.inodes.get(id).is_some_and(|inode| inode.name == name)
+        })
+    }
+
+    /// Creates every missing directory along `path`, like `mkdir -p`.
+    /// Returns `None` (and leaves existing state untouched) if a path
+    /// component already exists but is a `RegularFile`.
+    fn mkdir_p(&mut self, path: &str) -> Option<u64> {
+        let mut current = self.ensure_root();
+        for name in split_path(path) {
+            if self.inodes.get(&current)?.file_type != FileType::Directory {
+                println!("Error: '{}' is not a directory", name);
+                return None;
+            }
+            current = match self.find_child(current, name) {
+                Some(id) => id,
+                None => {
+                    let id = self.create_directory(name);
+                    self.add_file_to_directory(id, current);
+                    id
+                }
+            };
+        }
+
+        if self.inodes.get(&current)?.file_type != FileType::Directory {
+            println!("Error: '{}' exists and is not a directory", path);
+            return None;
+        }
+        Some(current)
+    }
+
+    /// Creates a regular file at `path`. The parent directory must already
+    /// exist (see `mkdir_p`), and `path`'s final component must be unique
+    /// within it.
+    fn create_file_at(&mut self, path: &str) -> Option<u64> {
+        let (parent_path, name) = split_parent(path)?;
+        let parent_id = if parent_path.is_empty() { self.ensure_root() } else { self.resolve(parent_path)? };
+
+        if self.inodes.get(&parent_id)?.file_type != FileType::Directory {
+            println!("Error: cannot create a child under a file");
+            return None;
+        }
+        if self.find_child(parent_id, name).is_some() {
+            println!("Error: '{}' already exists", path);
+            return None;
+        }
+
+        let file_id = self.create_file(name);
+        self.add_file_to_directory(file_id, parent_id);
+        Some(file_id)
+    }
+
+    /// Lists the immediate children of the directory at `path` as
+    /// `(name, file_type, id)` triples. Returns an empty list for a missing
+    /// path or a `RegularFile`, rather than panicking.
+    fn readdir(&self, path: &str) -> Vec<(String, FileType, u64)> {
+        let Some(entries) = self.resolve(path).and_then(|id| self.inodes.get(&id)).and_then(|inode| inode.entries.as_ref()) else {
+            return vec![];
+        };
+        entries
+            .iter()
+            .filter_map(|id| self.inodes.get(id))
+            .map(|inode| (inode.name.clone(), inode.file_type.clone(), inode.id))
+            .collect()
+    }
+
+    /// Returns size/type/id for `path` without panicking if it is missing.
+    fn stat(&self, path: &str) -> Option<InodeMeta> {
+        let inode = self.inodes.get(&self.resolve(path)?)?;
+        Some(InodeMeta { id: inode.id, size: inode.size, file_type: inode.file_type.clone() })
+    }
+
+    /// Writes the inode table to `path` as a zstd-compressed bincode image,
+    /// prefixed with a format version byte so incompatible layouts are
+    /// rejected on load instead of silently misread.
+    fn save(&self, path: &Path) {
+        let image = FileSystemImage {
+            next_id: self.next_id,
+            inodes: self.inodes.iter().map(|(&id, inode)| (id, (**inode).clone())).collect(),
+            blocks: self.blocks.iter().map(|(&id, block)| (id, block.to_vec())).collect(),
+            free_blocks: self.free_blocks.clone(),
+            next_block_id: self.next_block_id,
+        };
+        let encoded = bincode::serialize(&image).expect("failed to serialize filesystem image");
+        let compressed = zstd::stream::encode_all(&encoded[..], 0).expect("failed to compress filesystem image");
+
+        let mut file = File::create(path).expect("failed to create filesystem image file");
+        file.write_all(&[IMAGE_FORMAT_VERSION]).expect("failed to write image header");
+        file.write_all(&compressed).expect("failed to write filesystem image");
+    }
+
+    /// Reloads a filesystem image previously written by `save`. The journal
+    /// starts empty: an image is a point-in-time snapshot, not a replayable
+    /// log, so there is nothing to undo or replay immediately after a load.
+    fn load(path: &Path) -> FileSystem {
+        let mut raw = Vec::new();
+        File::open(path)
+            .expect("failed to open filesystem image file")
+            .read_to_end(&mut raw)
+            .expect("failed to read filesystem image file");
+
+        let (version, compressed) = raw.split_first().expect("empty filesystem image file");
+        assert_eq!(*version, IMAGE_FORMAT_VERSION, "unsupported filesystem image version: {}", version);
+
+        let encoded = zstd::stream::decode_all(compressed).expect("failed to decompress filesystem image");
+        let image: FileSystemImage = bincode::deserialize(&encoded).expect("failed to deserialize filesystem image");
+
+        FileSystem {
+            next_id: image.next_id,
+            inodes: image.inodes.into_iter().map(|(id, inode)| (id, Rc::new(inode))).collect(),
+            journal: Journal::new(),
+            blocks: image
+                .blocks
+                .into_iter()
+                .map(|(id, block)| {
+                    let mut fixed = [0u8; BLOCK_SIZE];
+                    let len = block.len().min(BLOCK_SIZE);
+                    fixed[..len].copy_from_slice(&block[..len]);
+                    (id, fixed)
+                })
+                .collect(),
+            free_blocks: image.free_blocks,
+            next_block_id: image.next_block_id,
+            snapshots: Vec::new(),
+        }
+    }
+
+    /// Captures the current inode and block state under `name`. Cloning the
+    /// inode map only bumps `Rc` refcounts for inodes that are unchanged
+    /// since; a later write copy-on-writes just the inode it touches,
+    /// leaving this snapshot's view intact.
+    fn snapshot(&mut self, name: &str) {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        self.snapshots.push(Snapshot {
+            name: name.to_string(),
+            timestamp,
+            next_id: self.next_id,
+            inodes: self.inodes.clone(),
+            blocks: self.blocks.clone(),
+            free_blocks: self.free_blocks.clone(),
+            next_block_id: self.next_block_id,
+        });
+    }
+
+    /// Lists snapshots as `(name, unix timestamp)`, oldest first.
+    fn list_snapshots(&self) -> Vec<(String, u64)> {
+        self.snapshots.iter().map(|s| (s.name.clone(), s.timestamp)).collect()
+    }
+
+    /// Restores the filesystem to the state captured by the most recent
+    /// snapshot named `name`. Returns `false` (leaving state untouched) if
+    /// no such snapshot exists.
+    fn restore(&mut self, name: &str) -> bool {
+        let Some(snapshot) = self.snapshots.iter().rev().find(|s| s.name == name) else {
+            return false;
+        };
+        self.next_id = snapshot.next_id;
+        self.inodes = snapshot.inodes.clone();
+        self.blocks = snapshot.blocks.clone();
+        self.free_blocks = snapshot.free_blocks.clone();
+        self.next_block_id = snapshot.next_block_id;
+        true
+    }
+
+    /// Zvault-style retention pruning: keeps the newest snapshot in each of
+    /// the most recent `daily` days, `weekly` weeks, `monthly` months, and
+    /// `yearly` years, and deletes every snapshot not selected by any of
+    /// those buckets. Returns the names removed.
+    fn prune_backups(&mut self, daily: usize, weekly: usize, monthly: usize, yearly: usize) -> Vec<String> {
+        let mut newest_first: Vec<&Snapshot> = self.snapshots.iter().collect();
+        newest_first.sort_by_key(|s| std::cmp::Reverse(s.timestamp));
+
+        let date_of = |s: &&Snapshot| -> DateTime<Utc> {
+            DateTime::from_timestamp(s.timestamp as i64, 0).unwrap_or_else(|| DateTime::from_timestamp(0, 0).unwrap())
+        };
+
+        let mut keep = select_into_buckets(&newest_first, daily, |s| {
+            let d = date_of(s);
+            (d.year(), d.ordinal())
+        });
+        keep.extend(select_into_buckets(&newest_first, weekly, |s| {
+            let week = date_of(s).iso_week();
+            (week.year(), week.week())
+        }));
+        keep.extend(select_into_buckets(&newest_first, monthly, |s| {
+            let d = date_of(s);
+            (d.year(), d.month())
+        }));
+        keep.extend(select_into_buckets(&newest_first, yearly, |s| (date_of(s).year(), 0)));
+
+        let removed: Vec<String> = self.snapshots.iter().map(|s| s.name.clone()).filter(|name| !keep.contains(name)).collect();
+        self.snapshots.retain(|s| keep.contains(&s.name));
+        removed
     }
 
     fn create_directory(&mut self, name: &str) -> u64 {
@@ -80,11 +640,14 @@ impl FileSystem {
             size: 0,
             file_type: FileType::Directory,
             direct_pointers: [None; NUM_DIRECT_POINTERS],
+            indirect_pointer: None,
+            double_indirect_pointer: None,
             entries: Some(Vec::new()),
-            data: None,
         };
-        self.inodes.insert(id, inode);
-        self.journal.log(format!("CREATE DIRECTORY: {}", name));
+        self.inodes.insert(id, Rc::new(inode));
+        self.journal.begin_txn();
+        self.journal.log(Operation::CreateDir { id, name: name.to_string() });
+        self.journal.commit_txn();
         id
     }
 
@@ -97,89 +660,434 @@ impl FileSystem {
             size: 0,
             file_type: FileType::RegularFile,
             direct_pointers: [None; NUM_DIRECT_POINTERS],
+            indirect_pointer: None,
+            double_indirect_pointer: None,
             entries: None,
-            data: None,
         };
-        self.inodes.insert(id, inode);
-        self.journal.log(format!("CREATE FILE: {}", name));
+        self.inodes.insert(id, Rc::new(inode));
+        self.journal.begin_txn();
+        self.journal.log(Operation::CreateFile { id, name: name.to_string() });
+        self.journal.commit_txn();
         id
     }
 
+    /// Reverses the most recent committed transaction by walking its
+    /// recorded operations in reverse and undoing each from its before-image.
     fn undo_last_operation(&mut self) {
-        if let Some(last_operation) = self.journal.undo() {
-            let parts: Vec<&str> = last_operation.split(':').map(|s| s.trim()).collect();
-            if parts.len() < 2 {
-                println!("Invalid journal entry: {}", last_operation);
+        match self.journal.entries.pop() {
+            Some(JournalEntry { operation: Operation::TxnCommit, .. }) => {}
+            Some(other) => {
+                self.journal.entries.push(other);
+                println!("Nothing to undo.");
+                return;
+            }
+            None => {
+                println!("Nothing to undo.");
                 return;
             }
+        }
 
-            match parts[0] {
-                "WRITE TO FILE" => {
-                    if let Ok(file_id) = parts[1].parse::<u64>() {
-                        if let Some(file) = self.inodes.get_mut(&file_id) {
-                            file.data = None;
-                            file.size = 0;
-                            println!("Undid write operation on file ID {}", file_id);
-                        }
+        let mut ops = Vec::new();
+        while let Some(entry) = self.journal.entries.pop() {
+            if matches!(entry.operation, Operation::TxnBegin) {
+                break;
+            }
+            ops.push(entry.operation);
+        }
+
+        for op in ops {
+            self.undo_operation(op);
+        }
+        println!("Undid last operation.");
+    }
+
+    fn undo_operation(&mut self, op: Operation) {
+        match op {
+            Operation::CreateFile { id, .. } | Operation::CreateDir { id, .. } => {
+                self.free_file_blocks(id);
+                self.inodes.remove(&id);
+            }
+            Operation::AddEntry { child, parent } => {
+                if let Some(dir) = self.inode_mut(parent) {
+                    if let Some(entries) = &mut dir.entries {
+                        entries.retain(|&entry| entry != child);
                     }
                 }
-                _ => println!("Undo not implemented for operation: {}", parts[0]),
             }
-        } else {
-            println!("Nothing to undo.");
+            Operation::Write { id, before, .. } => {
+                self.write_blocks(id, &before.unwrap_or_default());
+            }
+            Operation::TxnBegin | Operation::TxnCommit => {}
         }
     }
 
-
     fn add_file_to_directory(&mut self, file_id: u64, dir_id: u64) {
-        if let Some(dir) = self.inodes.get_mut(&dir_id) {
+        if let Some(dir) = self.inode_mut(dir_id) {
             if let Some(entries) = &mut dir.entries {
                 entries.push(file_id);
-                self.journal.log(format!("ADD FILE: {} TO DIRECTORY: {}", file_id, dir_id));
+                self.journal.begin_txn();
+                self.journal.log(Operation::AddEntry { child: file_id, parent: dir_id });
+                self.journal.commit_txn();
             }
         }
     }
 
     fn write_to_file(&mut self, file_id: u64, data: &[u8]) {
-        if let Some(file) = self.inodes.get_mut(&file_id) {
-            if file.file_type == FileType::RegularFile {
-                file.size = data.len() as u64;
-                file.data = Some(data.to_vec());
-                self.journal.log(format!("WRITE TO FILE: {}", file_id));
-            } else {
-                println!("Error: Cannot write to a directory!");
+        let Some(file) = self.inodes.get(&file_id) else {
+            return;
+        };
+        if file.file_type != FileType::RegularFile {
+            println!("Error: Cannot write to a directory!");
+            return;
+        }
+
+        let before = self.read_file(file_id);
+        self.write_blocks(file_id, data);
+
+        self.journal.begin_txn();
+        self.journal.log(Operation::Write { id: file_id, before: Some(before), after: data.to_vec() });
+        self.journal.commit_txn();
+    }
+
+    /// Rebuilds the inode table and block store from scratch by re-applying
+    /// only the transactions that reached a `TxnCommit` marker, discarding
+    /// any uncommitted tail left by a crash mid-operation. This is the
+    /// crash-recovery path: after `replay`, the inode map equals the state
+    /// at the last `TxnCommit`.
+    fn replay(&mut self) {
+        let entries = std::mem::take(&mut self.journal.entries);
+        self.inodes = HashMap::new();
+        self.blocks = HashMap::new();
+        self.free_blocks = Vec::new();
+        self.ensure_root();
+
+        let mut pending = Vec::new();
+        let mut in_txn = false;
+
+        for entry in &entries {
+            match &entry.operation {
+                Operation::TxnBegin => {
+                    pending.clear();
+                    in_txn = true;
+                }
+                Operation::TxnCommit => {
+                    if in_txn {
+                        for op in pending.drain(..) {
+                            self.apply_operation(op);
+                        }
+                    }
+                    in_txn = false;
+                }
+                op => {
+                    if in_txn {
+                        pending.push(op.clone());
+                    }
+                }
             }
         }
+
+        self.journal.entries = entries;
     }
 
+    fn apply_operation(&mut self, op: Operation) {
+        match op {
+            Operation::CreateFile { id, name } => {
+                self.inodes.insert(
+                    id,
+                    Rc::new(Inode {
+                        id,
+                        name,
+                        size: 0,
+                        file_type: FileType::RegularFile,
+                        direct_pointers: [None; NUM_DIRECT_POINTERS],
+                        indirect_pointer: None,
+                        double_indirect_pointer: None,
+                        entries: None,
+                    }),
+                );
+            }
+            Operation::CreateDir { id, name } => {
+                self.inodes.insert(
+                    id,
+                    Rc::new(Inode {
+                        id,
+                        name,
+                        size: 0,
+                        file_type: FileType::Directory,
+                        direct_pointers: [None; NUM_DIRECT_POINTERS],
+                        indirect_pointer: None,
+                        double_indirect_pointer: None,
+                        entries: Some(Vec::new()),
+                    }),
+                );
+            }
+            Operation::AddEntry { child, parent } => {
+                if let Some(dir) = self.inode_mut(parent) {
+                    if let Some(entries) = &mut dir.entries {
+                        entries.push(child);
+                    }
+                }
+            }
+            Operation::Write { id, after, .. } => {
+                self.write_blocks(id, &after);
+            }
+            Operation::TxnBegin | Operation::TxnCommit => {}
+        }
+    }
+
+    /// Walks an inode's direct pointers, then its indirect pointer's block
+    /// list, then its double-indirect pointer's indirect blocks, in order,
+    /// concatenating their contents and trimming to `size` so a
+    /// partially-filled final block doesn't leak padding.
     fn read_file(&self, file_id: u64) -> Vec<u8> {
-        if let Some(file) = self.inodes.get(&file_id) {
-            if let Some(data) = &file.data {
-                return data.clone();
+        let Some(inode) = self.inodes.get(&file_id) else {
+            return vec![];
+        };
+        if inode.file_type != FileType::RegularFile {
+            return vec![];
+        }
+
+        let mut block_ids: Vec<u64> = inode.direct_pointers.iter().filter_map(|p| *p).collect();
+        if let Some(indirect_id) = inode.indirect_pointer {
+            if let Some(indirect_block) = self.blocks.get(&indirect_id) {
+                block_ids.extend(decode_block_ids(indirect_block));
+            }
+        }
+        if let Some(double_indirect_id) = inode.double_indirect_pointer {
+            if let Some(double_indirect_block) = self.blocks.get(&double_indirect_id) {
+                for indirect_id in decode_block_ids(double_indirect_block) {
+                    if let Some(indirect_block) = self.blocks.get(&indirect_id) {
+                        block_ids.extend(decode_block_ids(indirect_block));
+                    }
+                }
+            }
+        }
+
+        let mut data = Vec::with_capacity(inode.size as usize);
+        for id in block_ids {
+            if let Some(block) = self.blocks.get(&id) {
+                data.extend_from_slice(block);
             }
         }
-        vec![]
+        data.truncate(inode.size as usize);
+        data
     }
 
     fn list_directories_and_files(&self) {
         for inode in self.inodes.values() {
-            match &inode.file_type {
-                FileType::Directory => {
-                    println!("Directory {} (ID: {}):", inode.name, inode.id);
-                    if let Some(entries) = &inode.entries {
-                        for entry_id in entries {
-                            if let Some(entry) = self.inodes.get(entry_id) {
-                                println!("- File {} (ID: {}, Size: {} bytes)", entry.name, entry.id, entry.size);
-                            }
+            if inode.file_type == FileType::Directory {
+                println!("Directory {} (ID: {}):", inode.name, inode.id);
+                if let Some(entries) = &inode.entries {
+                    for entry_id in entries {
+                        if let Some(entry) = self.inodes.get(entry_id) {
+                            println!("- File {} (ID: {}, Size: {} bytes)", entry.name, entry.id, entry.size);
                         }
                     }
                 }
-                _ => {}
             }
         }
     }
 }
 
+/// Adapts a `FileSystem` to the `fuser::Filesystem` trait so it can be
+/// mounted as a real directory.
+struct FuseAdapter<'a> {
+    fs: &'a mut FileSystem,
+}
+
+impl FuseAdapter<'_> {
+    fn file_attr(inode: &Inode) -> FileAttr {
+        let kind = match inode.file_type {
+            FileType::Directory => FuseFileType::Directory,
+            FileType::RegularFile => FuseFileType::RegularFile,
+        };
+        let perm = match inode.file_type {
+            FileType::Directory => 0o755,
+            FileType::RegularFile => 0o644,
+        };
+        FileAttr {
+            ino: inode.id,
+            size: inode.size,
+            blocks: inode.size.div_ceil(512),
+            atime: UNIX_EPOCH,
+            mtime: UNIX_EPOCH,
+            ctime: UNIX_EPOCH,
+            crtime: UNIX_EPOCH,
+            kind,
+            perm,
+            nlink: 1,
+            uid: 0,
+            gid: 0,
+            rdev: 0,
+            blksize: 512,
+            flags: 0,
+        }
+    }
+}
+
+impl FuseFilesystem for FuseAdapter<'_> {
+    fn lookup(&mut self, _req: &FuseRequest, parent: u64, name: &std::ffi::OsStr, reply: ReplyEntry) {
+        let Some(name) = name.to_str() else {
+            reply.error(libc::ENOENT);
+            return;
+        };
+        let Some(parent_inode) = self.fs.inodes.get(&parent) else {
+            reply.error(libc::ENOENT);
+            return;
+        };
+        let Some(entries) = &parent_inode.entries else {
+            reply.error(libc::ENOTDIR);
+            return;
+        };
+        let found = entries
+            .iter()
+            .filter_map(|id| self.fs.inodes.get(id))
+            .find(|inode| inode.name == name);
+        match found {
+            Some(inode) => reply.entry(&TTL, &Self::file_attr(inode), 0),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn getattr(&mut self, _req: &FuseRequest, ino: u64, reply: ReplyAttr) {
+        match self.fs.inodes.get(&ino) {
+            Some(inode) => reply.attr(&TTL, &Self::file_attr(inode)),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn read(
+        &mut self,
+        _req: &FuseRequest,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        match self.fs.inodes.get(&ino) {
+            Some(inode) if inode.file_type == FileType::RegularFile => {
+                let data = self.fs.read_file(ino);
+                let start = offset.max(0) as usize;
+                if start >= data.len() {
+                    reply.data(&[]);
+                    return;
+                }
+                let end = (start + size as usize).min(data.len());
+                reply.data(&data[start..end]);
+            }
+            Some(_) => reply.error(libc::EISDIR),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn readdir(&mut self, _req: &FuseRequest, ino: u64, _fh: u64, offset: i64, mut reply: ReplyDirectory) {
+        let Some(inode) = self.fs.inodes.get(&ino) else {
+            reply.error(libc::ENOENT);
+            return;
+        };
+        let Some(entry_ids) = &inode.entries else {
+            reply.error(libc::ENOTDIR);
+            return;
+        };
+
+        let mut rows: Vec<(u64, FuseFileType, String)> = vec![
+            (ino, FuseFileType::Directory, ".".to_string()),
+            (ino, FuseFileType::Directory, "..".to_string()),
+        ];
+        for child in entry_ids.iter().filter_map(|id| self.fs.inodes.get(id)) {
+            let kind = match child.file_type {
+                FileType::Directory => FuseFileType::Directory,
+                FileType::RegularFile => FuseFileType::RegularFile,
+            };
+            rows.push((child.id, kind, child.name.clone()));
+        }
+
+        for (i, (ino, kind, name)) in rows.into_iter().enumerate().skip(offset as usize) {
+            if reply.add(ino, (i + 1) as i64, kind, name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+
+    fn write(
+        &mut self,
+        _req: &FuseRequest,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        data: &[u8],
+        _write_flags: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyWrite,
+    ) {
+        let Some(inode) = self.fs.inodes.get(&ino) else {
+            reply.error(libc::ENOENT);
+            return;
+        };
+        if inode.file_type != FileType::RegularFile {
+            reply.error(libc::EISDIR);
+            return;
+        }
+
+        let start = offset.max(0) as usize;
+        let mut buf = self.fs.read_file(ino);
+        if buf.len() < start {
+            buf.resize(start, 0);
+        }
+        let end = start + data.len();
+        if buf.len() < end {
+            buf.resize(end, 0);
+        }
+        buf[start..end].copy_from_slice(data);
+        self.fs.write_to_file(ino, &buf);
+
+        reply.written(data.len() as u32);
+    }
+}
+
+
+fn main() {
+    let mut fs = FileSystem::new();
+
+    let dir1 = fs.create_directory("Documents");
+    let dir2 = fs.create_directory("Pictures");
+    let file1 = fs.create_file("doc1.txt");
+    let file2 = fs.create_file("doc2.txt");
+    let file3 = fs.create_file("pic1.jpg");
+
+    fs.add_file_to_directory(file1, dir1);
+    fs.add_file_to_directory(file2, dir1);
+    fs.add_file_to_directory(file3, dir2);
+
+    fs.write_to_file(file1, b"Hello, world!");
+
+    println!("\n=== Directory Listing ===");
+    fs.list_directories_and_files();
+
+    let data = fs.read_file(file1);
+    println!("\n=== Read File ===");
+    println!("File Data: {}", String::from_utf8_lossy(&data));
+
+    println!("\n=== Journal ===");
+    fs.journal.print_journal();
+
+    println!("\n=== Undo Operation ===");
+    if let Some(undone_operation) = fs.journal.undo() {
+        println!("Undid operation: {:?}", undone_operation);
+    } else {
+        println!("Nothing to undo.");
+    }
+
+    println!("\n=== Final Journal ===");
+    fs.journal.print_journal();
+}
+
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -209,6 +1117,52 @@ mod tests {
         assert_eq!(String::from_utf8_lossy(&data), "Hello, world!");
     }
 
+    #[test]
+    fn test_large_file_spans_indirect_pointer() {
+        let mut fs = FileSystem::new();
+        let file_id = fs.create_file("big.bin");
+        let data: Vec<u8> = (0..(NUM_DIRECT_POINTERS + 2) * BLOCK_SIZE + 17)
+            .map(|i| (i % 251) as u8)
+            .collect();
+
+        fs.write_to_file(file_id, &data);
+
+        let inode = fs.inodes.get(&file_id).unwrap();
+        assert!(inode.direct_pointers.iter().all(|p| p.is_some()));
+        assert!(inode.indirect_pointer.is_some());
+        assert_eq!(fs.read_file(file_id), data);
+    }
+
+    #[test]
+    fn test_huge_file_spans_double_indirect_pointer() {
+        let mut fs = FileSystem::new();
+        let file_id = fs.create_file("huge.bin");
+        let data: Vec<u8> = (0..(NUM_DIRECT_POINTERS + IDS_PER_BLOCK + 2) * BLOCK_SIZE)
+            .map(|i| (i % 251) as u8)
+            .collect();
+
+        fs.write_to_file(file_id, &data);
+
+        let inode = fs.inodes.get(&file_id).unwrap();
+        assert!(inode.indirect_pointer.is_some());
+        assert!(inode.double_indirect_pointer.is_some());
+        assert_eq!(fs.read_file(file_id), data);
+    }
+
+    #[test]
+    fn test_rewriting_file_frees_old_blocks() {
+        let mut fs = FileSystem::new();
+        let file_id = fs.create_file("scratch.bin");
+        fs.write_to_file(file_id, &vec![1u8; 3 * BLOCK_SIZE]);
+        let blocks_in_use = fs.blocks.len();
+
+        fs.write_to_file(file_id, b"short");
+
+        assert_eq!(fs.read_file(file_id), b"short");
+        assert!(fs.blocks.len() < blocks_in_use);
+        assert!(!fs.free_blocks.is_empty());
+    }
+
     #[test]
     fn test_add_file_to_directory() {
         let mut fs = FileSystem::new();
@@ -219,15 +1173,112 @@ mod tests {
         assert!(dir.entries.as_ref().unwrap().contains(&file_id));
     }
 
+    #[test]
+    fn test_mkdir_p_and_resolve() {
+        let mut fs = FileSystem::new();
+        let leaf_id = fs.mkdir_p("/a/b/c").unwrap();
+        assert_eq!(fs.resolve("/a/b/c"), Some(leaf_id));
+        assert_eq!(fs.resolve("a/b"), fs.resolve("/a/b"));
+
+        // Idempotent: re-running over an existing tree resolves to the same inode.
+        assert_eq!(fs.mkdir_p("/a/b/c"), Some(leaf_id));
+    }
+
+    #[test]
+    fn test_create_file_at_and_stat() {
+        let mut fs = FileSystem::new();
+        fs.mkdir_p("/docs").unwrap();
+        let file_id = fs.create_file_at("/docs/readme.txt").unwrap();
+        fs.write_to_file(file_id, b"hello");
+
+        let meta = fs.stat("/docs/readme.txt").unwrap();
+        assert_eq!(meta.id, file_id);
+        assert_eq!(meta.size, 5);
+        assert_eq!(meta.file_type, FileType::RegularFile);
+
+        assert_eq!(fs.stat("/does/not/exist"), None);
+        assert!(fs.create_file_at("/docs/readme.txt").is_none());
+        assert!(fs.create_file_at("/docs/readme.txt/child").is_none());
+    }
+
+    #[test]
+    fn test_readdir_lists_children() {
+        let mut fs = FileSystem::new();
+        fs.mkdir_p("/docs").unwrap();
+        fs.create_file_at("/docs/a.txt").unwrap();
+        fs.create_file_at("/docs/b.txt").unwrap();
+
+        let mut names: Vec<String> = fs.readdir("/docs").into_iter().map(|(name, ..)| name).collect();
+        names.sort();
+        assert_eq!(names, vec!["a.txt".to_string(), "b.txt".to_string()]);
+        assert!(fs.readdir("/nope").is_empty());
+    }
+
     #[test]
     fn test_journal_logging_and_undo() {
         let mut fs = FileSystem::new();
-        let dir_id = fs.create_directory("Logs");
-        assert_eq!(fs.journal.entries.len(), 1);
-        fs.journal.undo();
+        fs.create_directory("Logs");
+        // TxnBegin, CreateDir, TxnCommit
+        assert_eq!(fs.journal.entries.len(), 3);
+        fs.undo_last_operation();
         assert!(fs.journal.entries.is_empty());
     }
 
+    #[test]
+    fn test_undo_removes_created_inode() {
+        let mut fs = FileSystem::new();
+        let file_id = fs.create_file("scratch.txt");
+        fs.undo_last_operation();
+        assert!(!fs.inodes.contains_key(&file_id));
+    }
+
+    #[test]
+    fn test_replay_discards_uncommitted_tail() {
+        let mut fs = FileSystem::new();
+        let dir_id = fs.create_directory("Documents");
+        let file_id = fs.create_file("doc.txt");
+        fs.add_file_to_directory(file_id, dir_id);
+        fs.write_to_file(file_id, b"Hello, world!");
+
+        // Simulate a crash mid-transaction: an open TxnBegin with no matching commit.
+        fs.journal.begin_txn();
+        fs.journal.log(Operation::Write { id: file_id, before: None, after: b"uncommitted".to_vec() });
+
+        fs.replay();
+
+        assert_eq!(fs.read_file(file_id), b"Hello, world!");
+        assert!(fs.inodes.get(&dir_id).unwrap().entries.as_ref().unwrap().contains(&file_id));
+    }
+
+    #[test]
+    fn test_replay_reconnects_tree_to_root() {
+        let mut fs = FileSystem::new();
+        fs.mkdir_p("Documents");
+        let file_id = fs.create_file_at("Documents/doc.txt").unwrap();
+        fs.write_to_file(file_id, b"Hello, world!");
+
+        fs.replay();
+
+        assert_eq!(fs.resolve("Documents/doc.txt"), Some(file_id));
+        assert_eq!(fs.stat("Documents/doc.txt").unwrap().size, 13);
+        assert_eq!(fs.read_file(file_id), b"Hello, world!");
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let mut fs = FileSystem::new();
+        let file_id = fs.create_file("doc.txt");
+        fs.write_to_file(file_id, b"Hello, world!");
+
+        let path = std::env::temp_dir().join("sfs_save_load_roundtrip_test.img");
+        fs.save(&path);
+        let loaded = FileSystem::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.next_id, fs.next_id);
+        assert_eq!(loaded.read_file(file_id), b"Hello, world!");
+    }
+
     #[test]
     fn test_undo_last_operation() {
         let mut fs = FileSystem::new();
@@ -238,44 +1289,51 @@ mod tests {
         fs.undo_last_operation();
     
         // Ensure the file is still present but has no data
+        assert!(fs.read_file(file_id).is_empty());
         let file = fs.inodes.get(&file_id).unwrap();
-        assert!(file.data.is_none());
         assert_eq!(file.size, 0);
     }
-}
 
-fn main() {
-    let mut fs = FileSystem::new();
+    #[test]
+    fn test_snapshot_and_restore_roundtrip() {
+        let mut fs = FileSystem::new();
+        let file_id = fs.create_file("doc.txt");
+        fs.write_to_file(file_id, b"before");
+        fs.snapshot("before-edit");
 
-    let dir1 = fs.create_directory("Documents");
-    let dir2 = fs.create_directory("Pictures");
-    let file1 = fs.create_file("doc1.txt");
-    let file2 = fs.create_file("doc2.txt");
-    let file3 = fs.create_file("pic1.jpg");
+        fs.write_to_file(file_id, b"after");
+        assert_eq!(fs.read_file(file_id), b"after");
 
-    fs.add_file_to_directory(file1, dir1);
-    fs.add_file_to_directory(file2, dir1);
-    fs.add_file_to_directory(file3, dir2);
+        assert!(fs.restore("before-edit"));
+        assert_eq!(fs.read_file(file_id), b"before");
+    }
 
-    fs.write_to_file(file1, b"Hello, world!");
+    #[test]
+    fn test_restore_unknown_snapshot_leaves_state_untouched() {
+        let mut fs = FileSystem::new();
+        let file_id = fs.create_file("doc.txt");
+        fs.write_to_file(file_id, b"data");
 
-    println!("\n=== Directory Listing ===");
-    fs.list_directories_and_files();
+        assert!(!fs.restore("does-not-exist"));
+        assert_eq!(fs.read_file(file_id), b"data");
+    }
 
-    let data = fs.read_file(file1);
-    println!("\n=== Read File ===");
-    println!("File Data: {}", String::from_utf8_lossy(&data));
+    #[test]
+    fn test_prune_backups_keeps_newest_per_bucket() {
+        let mut fs = FileSystem::new();
+        fs.create_file("doc.txt");
 
-    println!("\n=== Journal ===");
-    fs.journal.print_journal();
+        for i in 0..5 {
+            fs.snapshot(&format!("snap{}", i));
+            fs.snapshots[i].timestamp = i as u64 * 86_400;
+        }
 
-    println!("\n=== Undo Operation ===");
-    if let Some(undone_operation) = fs.journal.undo() {
-        println!("Undid operation: {}", undone_operation);
-    } else {
-        println!("Nothing to undo.");
-    }
+        let removed = fs.prune_backups(2, 0, 0, 0);
 
-    println!("\n=== Final Journal ===");
-    fs.journal.print_journal();
+        assert_eq!(removed.len(), 3);
+        let remaining: Vec<String> = fs.list_snapshots().into_iter().map(|(name, _)| name).collect();
+        assert!(remaining.contains(&"snap3".to_string()));
+        assert!(remaining.contains(&"snap4".to_string()));
+        assert_eq!(remaining.len(), 2);
+    }
 }